@@ -0,0 +1,84 @@
+//! Writes to `/proc/<pid>/clear_refs` and diffs smaps snapshots to estimate a working set.
+
+use crate::SMap;
+use libc::pid_t;
+use std::fs::OpenOptions;
+use std::io::Write;
+
+/// The token written to `/proc/<pid>/clear_refs`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ClearRefsMode {
+    Referenced = 1,
+    Dirty = 2,
+    /// Pair with `VmFlags::SD` and a later pagemap read to find pages written since.
+    SoftDirty = 3,
+    PeakRss = 4,
+}
+
+pub fn clear_refs(pid: pid_t, mode: ClearRefsMode) -> Option<()> {
+    let path = format!("/proc/{}/clear_refs", pid);
+    let mut file = OpenOptions::new().write(true).open(path).ok()?;
+    write!(file, "{}", mode as u8).ok()
+}
+
+/// A mapping's estimated working-set contribution between two snapshots.
+#[derive(Debug, PartialEq)]
+pub struct WorkingSetDelta {
+    pub start: u64,
+    pub end: u64,
+    pub referenced_delta: i64,
+    pub private_dirty_delta: i64,
+}
+
+/// Diffs two smaps snapshots, matching mappings by their `start`/`end` range.
+pub fn working_set_delta(before: &[SMap], after: &[SMap]) -> Vec<WorkingSetDelta> {
+    after
+        .iter()
+        .filter_map(|after_map| {
+            let before_map = before.iter().find(|m| {
+                m.mapping.start == after_map.mapping.start && m.mapping.end == after_map.mapping.end
+            })?;
+            Some(WorkingSetDelta {
+                start: after_map.mapping.start,
+                end: after_map.mapping.end,
+                referenced_delta: after_map.referenced as i64 - before_map.referenced as i64,
+                private_dirty_delta: after_map.private_dirty as i64 - before_map.private_dirty as i64,
+            })
+        })
+        .collect()
+}
+
+#[test]
+fn test_working_set_delta() {
+    let mut before: SMap = Default::default();
+    before.mapping.start = 0x1000;
+    before.mapping.end = 0x2000;
+    before.referenced = 4096;
+    before.private_dirty = 0;
+
+    let mut after: SMap = Default::default();
+    after.mapping.start = 0x1000;
+    after.mapping.end = 0x2000;
+    after.referenced = 8192;
+    after.private_dirty = 4096;
+
+    let unrelated_before: SMap = Default::default();
+    let mut unrelated_after: SMap = Default::default();
+    unrelated_after.mapping.start = 0x3000;
+    unrelated_after.mapping.end = 0x4000;
+
+    let deltas = working_set_delta(
+        &[before, unrelated_before],
+        &[after, unrelated_after],
+    );
+
+    assert_eq!(
+        deltas,
+        vec![WorkingSetDelta {
+            start: 0x1000,
+            end: 0x2000,
+            referenced_delta: 4096,
+            private_dirty_delta: 4096,
+        }]
+    );
+}
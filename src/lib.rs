@@ -1,11 +1,21 @@
 use lazy_static::lazy_static;
 use libc::pid_t;
 use regex::Regex;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::fmt;
 use std::fs::File;
 use std::io::Read;
 use std::path::PathBuf;
+use std::str::FromStr;
+
+pub mod clear_refs;
+pub mod mem;
+pub mod pagemap;
+pub mod smaps_rollup;
 
 #[derive(Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Permissions {
     pub read: bool,
     pub write: bool,
@@ -50,79 +60,84 @@ impl Permissions {
     }
 }
 
-#[derive(Debug, Default, PartialEq)]
-pub struct VmFlags {
-    pub rd: bool,
-    pub wr: bool,
-    pub ex: bool,
-    pub sh: bool,
-    pub mr: bool,
-    pub mw: bool,
-    pub me: bool,
-    pub ms: bool,
-    pub gd: bool,
-    pub pf: bool,
-    pub dw: bool,
-    pub lo: bool,
-    pub io: bool,
-    pub sr: bool,
-    pub rr: bool,
-    pub dc: bool,
-    pub de: bool,
-    pub ac: bool,
-    pub nr: bool,
-    pub ht: bool,
-    pub sf: bool,
-    pub nl: bool,
-    pub ar: bool,
-    pub wf: bool,
-    pub dd: bool,
-    pub sd: bool,
-    pub mm: bool,
-    pub hg: bool,
-    pub nh: bool,
-    pub mg: bool,
-    pub um: bool,
-    pub uw: bool,
+bitflags::bitflags! {
+    // bitflags' own `serde` feature (enabled transitively by our `serde`
+    // feature) is what makes this derive possible on a flags type.
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+    pub struct VmFlags: u32 {
+        const RD = 1 << 0;
+        const WR = 1 << 1;
+        const EX = 1 << 2;
+        const SH = 1 << 3;
+        const MR = 1 << 4;
+        const MW = 1 << 5;
+        const ME = 1 << 6;
+        const MS = 1 << 7;
+        const GD = 1 << 8;
+        const PF = 1 << 9;
+        const DW = 1 << 10;
+        const LO = 1 << 11;
+        const IO = 1 << 12;
+        const SR = 1 << 13;
+        const RR = 1 << 14;
+        const DC = 1 << 15;
+        const DE = 1 << 16;
+        const AC = 1 << 17;
+        const NR = 1 << 18;
+        const HT = 1 << 19;
+        const SF = 1 << 20;
+        const NL = 1 << 21;
+        const AR = 1 << 22;
+        const WF = 1 << 23;
+        const DD = 1 << 24;
+        const SD = 1 << 25;
+        const MM = 1 << 26;
+        const HG = 1 << 27;
+        const NH = 1 << 28;
+        const MG = 1 << 29;
+        const UM = 1 << 30;
+        const UW = 1 << 31;
+    }
 }
 
 impl VmFlags {
     fn from_str(s: &str) -> VmFlags {
-        let mut flags: VmFlags = Default::default();
+        let mut flags = VmFlags::empty();
         for flag in s.split(" ") {
             match flag {
-                "rd" => flags.rd = true,
-                "wr" => flags.wr = true,
-                "ex" => flags.ex = true,
-                "sh" => flags.sh = true,
-                "mr" => flags.mr = true,
-                "mw" => flags.mw = true,
-                "me" => flags.me = true,
-                "ms" => flags.ms = true,
-                "gd" => flags.gd = true,
-                "pf" => flags.pf = true,
-                "dw" => flags.dw = true,
-                "lo" => flags.lo = true,
-                "io" => flags.io = true,
-                "sr" => flags.sr = true,
-                "rr" => flags.rr = true,
-                "dc" => flags.dc = true,
-                "de" => flags.de = true,
-                "ac" => flags.ac = true,
-                "nr" => flags.nr = true,
-                "ht" => flags.ht = true,
-                "sf" => flags.sf = true,
-                "nl" => flags.nl = true,
-                "ar" => flags.ar = true,
-                "wf" => flags.wf = true,
-                "dd" => flags.dd = true,
-                "sd" => flags.sd = true,
-                "mm" => flags.mm = true,
-                "hg" => flags.hg = true,
-                "nh" => flags.nh = true,
-                "mg" => flags.mg = true,
-                "um" => flags.um = true,
-                "uw" => flags.uw = true,
+                "rd" => flags |= VmFlags::RD,
+                "wr" => flags |= VmFlags::WR,
+                "ex" => flags |= VmFlags::EX,
+                "sh" => flags |= VmFlags::SH,
+                "mr" => flags |= VmFlags::MR,
+                "mw" => flags |= VmFlags::MW,
+                "me" => flags |= VmFlags::ME,
+                "ms" => flags |= VmFlags::MS,
+                "gd" => flags |= VmFlags::GD,
+                "pf" => flags |= VmFlags::PF,
+                "dw" => flags |= VmFlags::DW,
+                "lo" => flags |= VmFlags::LO,
+                "io" => flags |= VmFlags::IO,
+                "sr" => flags |= VmFlags::SR,
+                "rr" => flags |= VmFlags::RR,
+                "dc" => flags |= VmFlags::DC,
+                "de" => flags |= VmFlags::DE,
+                "ac" => flags |= VmFlags::AC,
+                "nr" => flags |= VmFlags::NR,
+                "ht" => flags |= VmFlags::HT,
+                "sf" => flags |= VmFlags::SF,
+                "nl" => flags |= VmFlags::NL,
+                "ar" => flags |= VmFlags::AR,
+                "wf" => flags |= VmFlags::WF,
+                "dd" => flags |= VmFlags::DD,
+                "sd" => flags |= VmFlags::SD,
+                "mm" => flags |= VmFlags::MM,
+                "hg" => flags |= VmFlags::HG,
+                "nh" => flags |= VmFlags::NH,
+                "mg" => flags |= VmFlags::MG,
+                "um" => flags |= VmFlags::UM,
+                "uw" => flags |= VmFlags::UW,
                 _ => {
                     // Ignore unknown flags so that if future versions of linux add additional flags
                     // the parsing won't break
@@ -136,33 +151,29 @@ impl VmFlags {
 #[test]
 fn test_vmflags_from_str() {
     // No flags enabled should parse correctly
-    assert_eq!(VmFlags::from_str(""), Default::default());
+    assert_eq!(VmFlags::from_str(""), VmFlags::empty());
 
     // Unknown flags should be ignored
-    assert_eq!(VmFlags::from_str("a b c d"), Default::default());
+    assert_eq!(VmFlags::from_str("a b c d"), VmFlags::empty());
 
     // Check enabling some subset of flags
-    let mut flags: VmFlags = Default::default();
-    flags.rd = true;
-    flags.de = true;
-    flags.uw = true;
+    let flags = VmFlags::RD | VmFlags::DE | VmFlags::UW;
     assert_eq!(VmFlags::from_str("rd de uw"), flags);
 
     // Check that the order of flags doesn't matter
-    let mut flags: VmFlags = Default::default();
-    flags.rd = true;
-    flags.de = true;
-    flags.uw = true;
+    let flags = VmFlags::RD | VmFlags::DE | VmFlags::UW;
     assert_eq!(VmFlags::from_str("uw rd de"), flags);
 }
 
 #[derive(Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Device {
     pub major: u64,
     pub minor: u64,
 }
 
 #[derive(Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Mapping {
     pub start: u64,
     pub end: u64,
@@ -191,23 +202,53 @@ lazy_static! {
     };
 }
 
-impl Mapping {
-    pub fn from_str(s: &str) -> Option<Mapping> {
-        let caps = RE.captures(s.trim())?;
-        let start = u64::from_str_radix(&caps[1], 16).ok()?;
-        let end = u64::from_str_radix(&caps[2], 16).ok()?;
+/// An error encountered while parsing a `maps`/`smaps` line.
+#[derive(Debug, PartialEq)]
+pub enum ParseError {
+    /// The line didn't match the expected `maps` format.
+    NoMatch,
+    /// A field matched the expected shape but its hex/decimal value couldn't
+    /// be parsed. Carries the name of the offending field.
+    InvalidField(&'static str),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::NoMatch => write!(f, "line did not match the maps format"),
+            ParseError::InvalidField(field) => write!(f, "malformed {} field", field),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl FromStr for Mapping {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Mapping, ParseError> {
+        let caps = RE.captures(s.trim()).ok_or(ParseError::NoMatch)?;
+        let start =
+            u64::from_str_radix(&caps[1], 16).map_err(|_| ParseError::InvalidField("start"))?;
+        let end =
+            u64::from_str_radix(&caps[2], 16).map_err(|_| ParseError::InvalidField("end"))?;
         let perms = Permissions::from_str(&caps[3]);
-        let offset = usize::from_str_radix(&caps[4], 16).ok()?;
+        let offset = usize::from_str_radix(&caps[4], 16)
+            .map_err(|_| ParseError::InvalidField("offset"))?;
 
-        let dev_major = u64::from_str_radix(&caps[5], 16).ok()?;
-        let dev_minor = u64::from_str_radix(&caps[6], 16).ok()?;
+        let dev_major = u64::from_str_radix(&caps[5], 16)
+            .map_err(|_| ParseError::InvalidField("device major"))?;
+        let dev_minor = u64::from_str_radix(&caps[6], 16)
+            .map_err(|_| ParseError::InvalidField("device minor"))?;
         let device = Device {
             major: dev_major,
             minor: dev_minor,
         };
-        let inode: u64 = caps[7].parse().ok()?;
+        let inode: u64 = caps[7]
+            .parse()
+            .map_err(|_| ParseError::InvalidField("inode"))?;
         let pathname = caps.get(8).map(|m| m.as_str().to_string());
-        Some(Mapping {
+        Ok(Mapping {
             start,
             end,
             perms,
@@ -221,15 +262,15 @@ impl Mapping {
 
 #[test]
 fn test_mapping_from_str() {
-    assert_eq!(Mapping::from_str(""), None);
-    assert_eq!(Mapping::from_str("    \n   "), None);
+    assert_eq!("".parse::<Mapping>(), Err(ParseError::NoMatch));
+    assert_eq!("    \n   ".parse::<Mapping>(), Err(ParseError::NoMatch));
     let mut perms: Permissions = Default::default();
     perms.read(true);
     perms.write(true);
     perms.private(true);
     assert_eq!(
-        Mapping::from_str("00e24000-011f7000 rw-p 00000000 00:00 0           [heap]"),
-        Some(Mapping {
+        "00e24000-011f7000 rw-p 00000000 00:00 0           [heap]".parse::<Mapping>(),
+        Ok(Mapping {
             start: 0x00e24000,
             end: 0x011f7000,
             perms,
@@ -246,8 +287,8 @@ fn test_mapping_from_str() {
     perms.private(true);
 
     assert_eq!(
-        Mapping::from_str("35b1a21000-35b1a22000 rw-p abcd ff:10 0"),
-        Some(Mapping {
+        "35b1a21000-35b1a22000 rw-p abcd ff:10 0".parse::<Mapping>(),
+        Ok(Mapping {
             start: 0x35b1a21000,
             end: 0x35b1a22000,
             perms,
@@ -263,6 +304,7 @@ fn test_mapping_from_str() {
 }
 
 #[derive(Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct SMap {
     pub mapping: Mapping,
     pub size: u64,
@@ -359,6 +401,20 @@ impl SMap {
     }
 }
 
+impl FromStr for SMap {
+    type Err = ParseError;
+
+    /// Parses a single `smaps` block: a `maps`-style header line followed by
+    /// its `Key: value` fields, up to (but not including) the next mapping's
+    /// header line.
+    fn from_str(s: &str) -> Result<SMap, ParseError> {
+        let mut lines = s.split('\n');
+        let mapping: Mapping = lines.next().ok_or(ParseError::NoMatch)?.parse()?;
+        let fields = lines.take_while(|l| l.parse::<Mapping>().is_err()).collect();
+        SMap::from_lines(mapping, fields).ok_or(ParseError::InvalidField("smaps"))
+    }
+}
+
 /// Returns mappings for a given pid
 pub fn from_pid(pid: pid_t) -> Option<Vec<SMap>> {
     let path = format!("/proc/{}/smaps", pid);
@@ -383,9 +439,9 @@ pub fn from_str(raw: &str) -> Option<Vec<SMap>> {
     let mut i = 0;
     while i < lines.len() {
         let mut smap_lines: Vec<&str> = Vec::new();
-        if let Some(map) = Mapping::from_str(lines[i]) {
+        if let Ok(map) = lines[i].parse::<Mapping>() {
             i += 1;
-            while i < lines.len() && Mapping::from_str(lines[i]).is_none() {
+            while i < lines.len() && lines[i].parse::<Mapping>().is_err() {
                 smap_lines.push(lines[i]);
                 i += 1;
             }
@@ -474,16 +530,13 @@ VmFlags:
                 referenced: 2796 * 1024,
                 anonymous: 2796 * 1024,
                 thp_eligible: 1,
-                vm_flags: VmFlags {
-                    rd: true,
-                    wr: true,
-                    mr: true,
-                    mw: true,
-                    me: true,
-                    ac: true,
-                    sd: true,
-                    ..Default::default()
-                },
+                vm_flags: VmFlags::RD
+                    | VmFlags::WR
+                    | VmFlags::MR
+                    | VmFlags::MW
+                    | VmFlags::ME
+                    | VmFlags::AC
+                    | VmFlags::SD,
                 ..Default::default()
             },
             SMap {
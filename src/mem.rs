@@ -0,0 +1,84 @@
+//! Reads the raw bytes backing a mapping out of `/proc/<pid>/mem`.
+
+use crate::Mapping;
+use libc::pid_t;
+use std::fmt;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+
+/// An error encountered while reading a mapping's bytes from `/proc/<pid>/mem`.
+#[derive(Debug)]
+pub enum ReadMappingError {
+    /// The mapping isn't readable, e.g. a `---p` guard page.
+    NotReadable,
+    /// The mapping's `end` is before its `start`.
+    InvalidRange,
+    /// Opening, seeking, or reading `/proc/<pid>/mem` failed.
+    Io(std::io::Error),
+}
+
+impl fmt::Display for ReadMappingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReadMappingError::NotReadable => write!(f, "mapping is not readable"),
+            ReadMappingError::InvalidRange => write!(f, "mapping end is before its start"),
+            ReadMappingError::Io(e) => write!(f, "failed to read /proc/<pid>/mem: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ReadMappingError {}
+
+impl From<std::io::Error> for ReadMappingError {
+    fn from(e: std::io::Error) -> Self {
+        ReadMappingError::Io(e)
+    }
+}
+
+/// Reads the raw bytes backing `mapping` out of `/proc/<pid>/mem`.
+pub fn read_mapping(pid: pid_t, mapping: &Mapping) -> Result<Vec<u8>, ReadMappingError> {
+    if !mapping.perms.read {
+        return Err(ReadMappingError::NotReadable);
+    }
+    if mapping.end < mapping.start {
+        return Err(ReadMappingError::InvalidRange);
+    }
+
+    let path = format!("/proc/{}/mem", pid);
+    let mut file = File::open(path)?;
+    file.seek(SeekFrom::Start(mapping.start))?;
+
+    let mut buf = vec![0u8; (mapping.end - mapping.start) as usize];
+    file.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+#[test]
+fn test_read_mapping_not_readable() {
+    let mapping = Mapping {
+        end: 0x1000,
+        ..Default::default()
+    };
+
+    assert!(matches!(
+        read_mapping(1, &mapping),
+        Err(ReadMappingError::NotReadable)
+    ));
+}
+
+#[test]
+fn test_read_mapping_rejects_inverted_range() {
+    let mut perms: crate::Permissions = Default::default();
+    perms.read(true);
+    let mapping = Mapping {
+        perms,
+        start: 0x2000,
+        end: 0x1000,
+        ..Default::default()
+    };
+
+    assert!(matches!(
+        read_mapping(1, &mapping),
+        Err(ReadMappingError::InvalidRange)
+    ));
+}
@@ -0,0 +1,130 @@
+//! Reads `/proc/<pid>/pagemap` to resolve per-page residency and swap state.
+
+use crate::{Mapping, SMap};
+use libc::pid_t;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+
+const PAGE_SIZE: u64 = 4096;
+
+const PM_PRESENT: u64 = 1 << 63;
+const PM_SWAPPED: u64 = 1 << 62;
+const PM_FILE_OR_SHARED_ANON: u64 = 1 << 61;
+const PM_SOFT_DIRTY: u64 = 1 << 55;
+const PM_MMAP_EXCLUSIVE: u64 = 1 << 56;
+const PFN_MASK: u64 = (1 << 55) - 1;
+const SWAP_TYPE_MASK: u64 = (1 << 5) - 1;
+const SWAP_OFFSET_MASK: u64 = (1 << 50) - 1;
+
+/// The decoded state of a single virtual page.
+#[derive(Debug, PartialEq)]
+pub enum PageMapEntry {
+    Present {
+        // Reads as zero without CAP_SYS_ADMIN.
+        pfn: u64,
+        file_or_shared_anon: bool,
+        exclusive: bool,
+        soft_dirty: bool,
+    },
+    Swapped {
+        swap_type: u64,
+        offset: u64,
+        soft_dirty: bool,
+    },
+    Absent,
+}
+
+impl PageMapEntry {
+    fn from_u64(raw: u64) -> PageMapEntry {
+        let soft_dirty = raw & PM_SOFT_DIRTY != 0;
+        if raw & PM_PRESENT != 0 {
+            PageMapEntry::Present {
+                pfn: raw & PFN_MASK,
+                file_or_shared_anon: raw & PM_FILE_OR_SHARED_ANON != 0,
+                exclusive: raw & PM_MMAP_EXCLUSIVE != 0,
+                soft_dirty,
+            }
+        } else if raw & PM_SWAPPED != 0 {
+            PageMapEntry::Swapped {
+                swap_type: raw & SWAP_TYPE_MASK,
+                offset: (raw >> 5) & SWAP_OFFSET_MASK,
+                soft_dirty,
+            }
+        } else {
+            PageMapEntry::Absent
+        }
+    }
+}
+
+/// Reads one pagemap entry per virtual page spanned by `mapping`.
+pub fn from_pid_mapping(pid: pid_t, mapping: &Mapping) -> Option<Vec<PageMapEntry>> {
+    if mapping.end < mapping.start {
+        return None;
+    }
+
+    let path = format!("/proc/{}/pagemap", pid);
+    let mut file = File::open(path).ok()?;
+
+    let start_page = mapping.start / PAGE_SIZE;
+    let num_pages = (mapping.end - mapping.start) / PAGE_SIZE;
+
+    file.seek(SeekFrom::Start(start_page * 8)).ok()?;
+
+    let mut buf = vec![0u8; (num_pages * 8) as usize];
+    file.read_exact(&mut buf).ok()?;
+
+    Some(
+        buf.chunks_exact(8)
+            .map(|chunk| PageMapEntry::from_u64(u64::from_le_bytes(chunk.try_into().unwrap())))
+            .collect(),
+    )
+}
+
+/// Reads one pagemap entry per virtual page spanned by `smap`'s mapping.
+pub fn from_pid_smap(pid: pid_t, smap: &SMap) -> Option<Vec<PageMapEntry>> {
+    from_pid_mapping(pid, &smap.mapping)
+}
+
+#[test]
+fn test_from_pid_mapping_rejects_inverted_range() {
+    let mapping = Mapping {
+        start: 0x2000,
+        end: 0x1000,
+        ..Default::default()
+    };
+    assert_eq!(from_pid_mapping(1, &mapping), None);
+}
+
+#[test]
+fn test_pagemap_entry_present() {
+    // present, soft-dirty, exclusive, pfn = 0x1234
+    let raw = PM_PRESENT | PM_SOFT_DIRTY | PM_MMAP_EXCLUSIVE | 0x1234;
+    assert_eq!(
+        PageMapEntry::from_u64(raw),
+        PageMapEntry::Present {
+            pfn: 0x1234,
+            file_or_shared_anon: false,
+            exclusive: true,
+            soft_dirty: true,
+        }
+    );
+}
+
+#[test]
+fn test_pagemap_entry_swapped() {
+    // swapped, type = 3, offset = 42
+    let raw = PM_SWAPPED | (42 << 5) | 3;
+    assert_eq!(
+        PageMapEntry::from_u64(raw),
+        PageMapEntry::Swapped {
+            swap_type: 3,
+            offset: 42,
+            soft_dirty: false,
+        }
+    );
+}
+
+#[test]
+fn test_pagemap_entry_absent() {
+    assert_eq!(PageMapEntry::from_u64(0), PageMapEntry::Absent);
+}
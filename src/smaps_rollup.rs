@@ -0,0 +1,62 @@
+//! Parses `/proc/<pid>/smaps_rollup`, the kernel's pre-aggregated memory summary for a whole process.
+
+use crate::{Mapping, SMap};
+use libc::pid_t;
+use std::fs::File;
+use std::io::Read;
+
+/// A `smaps_rollup` summary; same shape as an `SMap`, but `mapping` spans the whole address space.
+pub type Rollup = SMap;
+
+pub fn rollup_from_str(raw: &str) -> Option<Rollup> {
+    let lines: Vec<&str> = raw.split('\n').collect();
+    let mapping = lines.first()?.parse::<Mapping>().ok()?;
+    SMap::from_lines(mapping, lines[1..].to_vec())
+}
+
+/// Returns the aggregated rollup summary for a given pid.
+pub fn from_pid_rollup(pid: pid_t) -> Option<Rollup> {
+    let path = format!("/proc/{}/smaps_rollup", pid);
+    let mut file = File::open(path).ok()?;
+    let mut input = String::new();
+    file.read_to_string(&mut input).ok()?;
+    rollup_from_str(&input)
+}
+
+#[test]
+fn test_rollup_from_str() {
+    let txt = "\
+00000000-7fffffffffff r--p 00000000 00:00 0                              [rollup]
+Rss:               10000 kB
+Pss:                 9000 kB
+Pss_Dirty:           8000 kB
+Shared_Clean:           0 kB
+Shared_Dirty:           0 kB
+Private_Clean:          0 kB
+Private_Dirty:       8000 kB
+Referenced:          9000 kB
+Anonymous:           8000 kB
+LazyFree:               0 kB
+AnonHugePages:          0 kB
+ShmemPmdMapped:         0 kB
+FilePmdMapped:          0 kB
+Shared_Hugetlb:         0 kB
+Private_Hugetlb:        0 kB
+Swap:                   0 kB
+SwapPss:                0 kB
+Locked:                 0 kB
+";
+
+    let rollup = rollup_from_str(txt).unwrap();
+    assert_eq!(rollup.mapping.start, 0);
+    assert_eq!(rollup.mapping.end, 0x7fffffffffff);
+    assert_eq!(rollup.mapping.pathname, Some("[rollup]".to_string()));
+    assert_eq!(rollup.rss, 10000 * 1024);
+    assert_eq!(rollup.pss, 9000 * 1024);
+    assert_eq!(rollup.swap, 0);
+}
+
+#[test]
+fn test_rollup_from_str_empty() {
+    assert_eq!(rollup_from_str(""), None);
+}